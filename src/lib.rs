@@ -1,10 +1,78 @@
 // We want to make sure we are getting the shortest match possible
 // without getting tripped up by pathological cases.
 pub mod minspan {
+    use smallvec::SmallVec;
 
-    pub fn span<A>(query: &Vec<A>, history: &Vec<A>) -> Option<(usize, usize)>
+    /// A matched window in the history, with inclusive `start` and `end`
+    /// indices. Like pest's span, the `end` is part of the match, so a
+    /// single-element window has `start == end` and `len() == 1`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Span {
+        start: usize,
+        end: usize,
+    }
+
+    impl Span {
+        /// Build a span, validating that `start <= end` and that `end` is in
+        /// bounds for a history of length `history_len`.
+        pub fn new(history_len: usize, start: usize, end: usize) -> Option<Span> {
+            if start <= end && end < history_len {
+                Some(Span { start, end })
+            } else {
+                None
+            }
+        }
+
+        /// The inclusive start index.
+        pub fn start(&self) -> usize {
+            self.start
+        }
+
+        /// The inclusive end index.
+        pub fn end(&self) -> usize {
+            self.end
+        }
+
+        /// The number of elements covered (inclusive, `end - start + 1`).
+        pub fn len(&self) -> usize {
+            self.end - self.start + 1
+        }
+
+        /// A span always covers at least one element, so this is never true;
+        /// provided for symmetry with the rest of the standard-library idioms.
+        pub fn is_empty(&self) -> bool {
+            false
+        }
+
+        /// Re-borrow the matched window out of `history`.
+        pub fn as_slice<'h, A>(&self, history: &'h [A]) -> &'h [A] {
+            &history[self.start..=self.end]
+        }
+    }
+
+    impl From<Span> for (usize, usize) {
+        fn from(span: Span) -> Self {
+            (span.start, span.end)
+        }
+    }
+
+    pub fn span<A>(query: &[A], history: &[A]) -> Option<(usize, usize)>
     where
         A: PartialEq,
+    {
+        span_by(query, history, |a, b| a == b)
+    }
+
+    /// Like [`span`], but matches elements with a caller-supplied predicate
+    /// instead of `PartialEq`.
+    ///
+    /// This unlocks case-insensitive matching, Unicode-normalized comparison,
+    /// or matching elements on part of their value (e.g. comparing tokens by
+    /// kind while ignoring the data they carry) without wrapping everything in
+    /// a newtype. The query and the history share the element type `A`.
+    pub fn span_by<A, F>(query: &[A], history: &[A], eq: F) -> Option<(usize, usize)>
+    where
+        F: Fn(&A, &A) -> bool,
     {
         // If history is empty, we cannot find any span with valid indices.
         if history.is_empty() {
@@ -24,7 +92,7 @@ pub mod minspan {
         // Main loop: requires non-empty query and history
         for (bodyindex, bodychr) in history.iter().enumerate() {
             for (keyindex, keychr) in query.iter().enumerate().rev() {
-                if keychr == bodychr {
+                if eq(keychr, bodychr) {
                     // we have a match, therefore record it: it ends at bodyindex,
                     // and by construction, starts at starting_at[0]
                     starting_at[keyindex] = if keyindex == 0 {
@@ -53,6 +121,209 @@ pub mod minspan {
         }
         best_complete_solution
     }
+
+    /// Like [`span`], but also reports where each query element was matched
+    /// inside the minimal window.
+    ///
+    /// The returned `Vec<usize>` holds, in query order, the index in `history`
+    /// at which each query element was consumed. Fuzzy-finder UIs use these to
+    /// bold the matched characters instead of re-walking the subsequence.
+    pub fn span_positions<A>(query: &[A], history: &[A]) -> Option<(usize, usize, Vec<usize>)>
+    where
+        A: PartialEq,
+    {
+        if history.is_empty() {
+            return None;
+        }
+        if query.is_empty() {
+            return Some((0, 0, Vec::new()));
+        }
+
+        // Alongside each partial solution we remember the matched index for
+        // every query position reached so far, so the final window carries its
+        // own backpointers rather than needing a second subsequence walk.
+        let mut starting_at: Vec<Option<(usize, Vec<usize>)>> =
+            query.iter().map(|_| None).collect();
+        let mut best_complete_solution: Option<(usize, usize, Vec<usize>)> = None;
+
+        for (bodyindex, bodychr) in history.iter().enumerate() {
+            for (keyindex, keychr) in query.iter().enumerate().rev() {
+                if keychr == bodychr {
+                    let next = if keyindex == 0 {
+                        Some((bodyindex, vec![bodyindex]))
+                    } else {
+                        starting_at[keyindex - 1].as_ref().map(|(start, positions)| {
+                            let mut positions = positions.clone();
+                            positions.push(bodyindex);
+                            (*start, positions)
+                        })
+                    };
+                    starting_at[keyindex] = next;
+                    if (keyindex + 1) == query.len() {
+                        if let Some((from, positions)) = &starting_at[keyindex] {
+                            let from = *from;
+                            let to = bodyindex;
+                            best_complete_solution = match best_complete_solution {
+                                None => Some((from, to, positions.clone())),
+                                Some((currfrom, currto, currpos)) => {
+                                    if to - from < currto - currfrom {
+                                        Some((from, to, positions.clone()))
+                                    } else {
+                                        Some((currfrom, currto, currpos))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        best_complete_solution
+    }
+
+    /// Find the minimal window while consuming `history` lazily.
+    ///
+    /// The core pass only ever keeps `starting_at` (length `query.len()`) as
+    /// state, so it never needs the whole history in memory — this lets callers
+    /// scan log files, network streams, or other large sources without first
+    /// collecting them into a `Vec`. For the short queries that dominate fuzzy
+    /// matching the state lives inline in a [`SmallVec`], avoiding any heap
+    /// allocation.
+    pub fn span_streaming<A, I>(query: &[A], history: I) -> Option<(usize, usize)>
+    where
+        A: PartialEq,
+        I: IntoIterator<Item = A>,
+    {
+        let mut history = history.into_iter().enumerate();
+
+        // An empty query matches at the start, as long as the history is
+        // non-empty (mirroring `span`).
+        if query.is_empty() {
+            return history.next().map(|_| (0, 0));
+        }
+
+        let mut starting_at: SmallVec<[Option<(usize, usize)>; 16]> =
+            query.iter().map(|_| None).collect();
+        let mut best_complete_solution: Option<(usize, usize)> = None;
+
+        for (bodyindex, bodychr) in history {
+            for (keyindex, keychr) in query.iter().enumerate().rev() {
+                if keychr == &bodychr {
+                    starting_at[keyindex] = if keyindex == 0 {
+                        Some((bodyindex, bodyindex))
+                    } else {
+                        starting_at[keyindex - 1].map(|(start, _end)| (start, bodyindex))
+                    };
+                    if (keyindex + 1) == query.len() {
+                        if let Some((from, to)) = starting_at[keyindex] {
+                            best_complete_solution = match best_complete_solution {
+                                None => Some((from, to)),
+                                Some((currfrom, currto)) => Some(if to - from < currto - currfrom {
+                                    (from, to)
+                                } else {
+                                    (currfrom, currto)
+                                }),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        best_complete_solution
+    }
+
+    /// Locate the minimal window as a typed [`Span`] rather than a bare tuple.
+    ///
+    /// This is the self-documenting counterpart to [`span`], which is retained
+    /// as a tuple-returning shim for existing callers.
+    pub fn span_typed<A>(query: &[A], history: &[A]) -> Option<Span>
+    where
+        A: PartialEq,
+    {
+        span(query, history).and_then(|(start, end)| Span::new(history.len(), start, end))
+    }
+
+    /// Return every window whose length equals the global minimal span length.
+    ///
+    /// [`span`] keeps only the first of several equally-tight matches; search
+    /// UIs that want to highlight or cycle through all equally-good occurrences
+    /// need the complete set. We run the existing pass once to learn the
+    /// minimal length, then a second pass collecting every completed window of
+    /// that length, deduplicating identical ones.
+    pub fn all_min_spans<A>(query: &[A], history: &[A]) -> Vec<(usize, usize)>
+    where
+        A: PartialEq,
+    {
+        let min_len = match span(query, history) {
+            None => return Vec::new(),
+            Some((from, to)) => to - from + 1,
+        };
+        // An empty query degenerates to the single window `span` reports.
+        if query.is_empty() {
+            return vec![(0, 0)];
+        }
+
+        let mut starting_at: Vec<Option<(usize, usize)>> = query.iter().map(|_| None).collect();
+        let mut solutions: Vec<(usize, usize)> = Vec::new();
+
+        for (bodyindex, bodychr) in history.iter().enumerate() {
+            for (keyindex, keychr) in query.iter().enumerate().rev() {
+                if keychr == bodychr {
+                    starting_at[keyindex] = if keyindex == 0 {
+                        Some((bodyindex, bodyindex))
+                    } else {
+                        starting_at[keyindex - 1].map(|(start, _end)| (start, bodyindex))
+                    };
+                    if (keyindex + 1) == query.len() {
+                        if let Some((from, to)) = starting_at[keyindex] {
+                            if to - from + 1 == min_len && !solutions.contains(&(from, to)) {
+                                solutions.push((from, to));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        solutions
+    }
+
+    /// Score how well `query` matches `choice`, in the range `0.0..=1.0`.
+    ///
+    /// The minimal span is located with [`span`]; a perfectly contiguous match
+    /// scores highest, spread-out matches less, and shorter candidates are
+    /// favored overall. An empty query always matches (`1.0`); no match at all
+    /// scores `0.0`.
+    pub fn score<A>(query: &[A], choice: &[A]) -> f64
+    where
+        A: PartialEq,
+    {
+        if query.is_empty() {
+            return 1.0;
+        }
+        match span(query, choice) {
+            None => 0.0,
+            Some((from, to)) => {
+                let span_len = (to - from + 1) as f64;
+                let base = query.len() as f64 / span_len;
+                base / choice.len() as f64
+            }
+        }
+    }
+
+    /// Score every candidate in `choices` against `query` and return them
+    /// sorted from best to worst, dropping candidates that do not match.
+    pub fn rank<'a, A>(query: &[A], choices: &'a [Vec<A>]) -> Vec<(&'a Vec<A>, f64)>
+    where
+        A: PartialEq,
+    {
+        let mut ranked: Vec<(&'a Vec<A>, f64)> = choices
+            .iter()
+            .map(|choice| (choice, score(query, choice)))
+            .filter(|(_, s)| *s > 0.0)
+            .collect();
+        ranked.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
+        ranked
+    }
 }
 
 // Add proptest imports for property-based testing
@@ -93,6 +364,123 @@ mod tests {
         assert_eq!(run_span("", ""), None); // Both empty
     }
 
+    #[test]
+    fn test_span_positions_reports_matched_indices() {
+        let to_vec = |s: &str| s.chars().collect::<Vec<char>>();
+
+        // contiguous match: positions are the window itself
+        let (from, to, positions) =
+            minspan::span_positions(&to_vec("ab"), &to_vec("xabz")).unwrap();
+        assert_eq!((from, to), (1, 2));
+        assert_eq!(positions, vec![1, 2]);
+
+        // spread-out match picks the tightest window and its exact hits
+        let (from, to, positions) =
+            minspan::span_positions(&to_vec("curl"), &to_vec("acccccurlycurrelly")).unwrap();
+        assert_eq!(1 + to - from, 4);
+        assert_eq!(positions, vec![from, from + 1, from + 2, from + 3]);
+
+        // no match and the empty/edge cases mirror `span`
+        assert_eq!(minspan::span_positions(&to_vec("z"), &to_vec("abc")), None);
+        assert_eq!(
+            minspan::span_positions(&to_vec(""), &to_vec("abc")),
+            Some((0, 0, vec![]))
+        );
+        assert_eq!(minspan::span_positions(&to_vec("a"), &to_vec("")), None);
+    }
+
+    #[test]
+    fn test_score_and_rank() {
+        let to_vec = |s: &str| s.chars().collect::<Vec<char>>();
+
+        // empty query always matches, missing letters never do
+        assert_eq!(minspan::score(&to_vec(""), &to_vec("abc")), 1.0);
+        assert_eq!(minspan::score(&to_vec("z"), &to_vec("abc")), 0.0);
+
+        // a contiguous match gives base 1.0, scaled by candidate length
+        assert_eq!(minspan::score(&to_vec("ab"), &to_vec("ab")), 0.5);
+
+        // tighter / shorter candidates rank ahead of looser / longer ones
+        let choices = vec![to_vec("axxxb"), to_vec("ab"), to_vec("zzz")];
+        let ranked = minspan::rank(&to_vec("ab"), &choices);
+        let order: Vec<&Vec<char>> = ranked.iter().map(|(c, _)| *c).collect();
+        assert_eq!(order, vec![&to_vec("ab"), &to_vec("axxxb")]);
+    }
+
+    #[test]
+    fn test_span_type() {
+        let to_vec = |s: &str| s.chars().collect::<Vec<char>>();
+        let history = to_vec("xabz");
+
+        let span = minspan::span_typed(&to_vec("ab"), &history).unwrap();
+        assert_eq!(span.start(), 1);
+        assert_eq!(span.end(), 2);
+        assert_eq!(span.len(), 2);
+        assert!(!span.is_empty());
+        assert_eq!(span.as_slice(&history), &to_vec("ab")[..]);
+        assert_eq!(<(usize, usize)>::from(span), (1, 2));
+
+        // the constructor rejects out-of-range and inverted bounds
+        assert!(minspan::Span::new(4, 1, 2).is_some());
+        assert!(minspan::Span::new(4, 2, 1).is_none());
+        assert!(minspan::Span::new(4, 0, 4).is_none());
+    }
+
+    #[test]
+    fn test_span_streaming_matches_span() {
+        let to_vec = |s: &str| s.chars().collect::<Vec<char>>();
+
+        for (needle, haystack) in [("curl", "acccccurlycurrelly"), ("aba", "abababa"), ("z", "abc")] {
+            let query = to_vec(needle);
+            let history = to_vec(haystack);
+            assert_eq!(
+                minspan::span_streaming(&query, history.iter().cloned()),
+                minspan::span(&query, &history),
+                "streaming disagreed with span for {:?} in {:?}",
+                needle,
+                haystack
+            );
+        }
+
+        assert_eq!(minspan::span_streaming(&to_vec(""), "abc".chars()), Some((0, 0)));
+        assert_eq!(minspan::span_streaming(&to_vec(""), "".chars()), None);
+    }
+
+    #[test]
+    fn test_span_by_case_insensitive() {
+        let to_vec = |s: &str| s.chars().collect::<Vec<char>>();
+        let query = to_vec("CURL");
+        let history = to_vec("acccccurlycurrelly");
+
+        // exact equality finds nothing because the case differs
+        assert_eq!(minspan::span(&query, &history), None);
+
+        // a case-insensitive predicate recovers the same window `span` finds
+        // for the lowercase query
+        let ci = minspan::span_by(&query, &history, |a, b| {
+            a.eq_ignore_ascii_case(b)
+        });
+        assert_eq!(ci, minspan::span(&to_vec("curl"), &history));
+    }
+
+    #[test]
+    fn test_all_min_spans() {
+        let to_vec = |s: &str| s.chars().collect::<Vec<char>>();
+
+        // "aa" occurs at the same minimal length in three places
+        let spans = minspan::all_min_spans(&to_vec("aa"), &to_vec("aaaa"));
+        assert_eq!(spans, vec![(0, 1), (1, 2), (2, 3)]);
+
+        // a unique tightest window yields just that window
+        assert_eq!(
+            minspan::all_min_spans(&to_vec("curl"), &to_vec("acccccurlycurrelly")),
+            vec![minspan::span(&to_vec("curl"), &to_vec("acccccurlycurrelly")).unwrap()]
+        );
+
+        // no match yields nothing
+        assert_eq!(minspan::all_min_spans(&to_vec("z"), &to_vec("abc")), Vec::new());
+    }
+
     #[test]
     fn test_is_subsequence_consumes_main_seq() {
         let sub: Vec<char> = vec!['a', 'a', 'a'];